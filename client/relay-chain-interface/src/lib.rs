@@ -52,6 +52,8 @@ pub enum RelayChainError {
 	StateMachineError(Box<dyn sp_state_machine::Error>),
 	#[error("Unable to call RPC method '{0}' due to error: {1}")]
 	RpcCallError(String, JsonRpcError),
+	#[error("Timeout while waiting for the response to RPC method '{0}'")]
+	RpcTimeout(String),
 	#[error("RPC Error: '{0}'")]
 	JsonRpcError(#[from] JsonRpcError),
 	#[error("Unable to reach RpcStreamWorker: {0}")]
@@ -0,0 +1,104 @@
+// Copyright 2022 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use substrate_prometheus_endpoint::{
+	exponential_buckets, prometheus::HistogramTimer, register, CounterVec, HistogramOpts,
+	HistogramVec, Opts, PrometheusError, Registry, U64,
+};
+
+/// Metrics of the requests sent by the [`RelayChainRpcClient`](crate::RelayChainRpcClient).
+#[derive(Clone)]
+pub(crate) struct RelayChainRpcMetrics {
+	/// Duration of the requests per RPC method. Also counts the requests.
+	rpc_request: HistogramVec,
+	/// Number of failed requests per RPC method.
+	rpc_request_errors: CounterVec<U64>,
+}
+
+impl RelayChainRpcMetrics {
+	pub(crate) fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			rpc_request: register(
+				HistogramVec::new(
+					HistogramOpts::new(
+						"cumulus_relay_chain_rpc_request_duration",
+						"Duration of requests sent to the relay chain RPC node",
+					)
+					.buckets(
+						exponential_buckets(0.001, 4.0, 9)
+							.expect("function parameters are constant and always valid; qed"),
+					),
+					&["method"],
+				)?,
+				registry,
+			)?,
+			rpc_request_errors: register(
+				CounterVec::new(
+					Opts::new(
+						"cumulus_relay_chain_rpc_request_errors",
+						"Number of failed requests sent to the relay chain RPC node",
+					),
+					&["method"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Start a timer for a request to `method`. The duration is observed when the timer is
+	/// dropped.
+	pub(crate) fn start_request_timer(&self, method: &str) -> HistogramTimer {
+		self.rpc_request.with_label_values(&[method]).start_timer()
+	}
+
+	/// Note that a request to `method` failed.
+	pub(crate) fn on_request_error(&self, method: &str) {
+		self.rpc_request_errors.with_label_values(&[method]).inc();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn requests_and_errors_are_recorded_per_method() {
+		let registry = Registry::new();
+		let metrics = RelayChainRpcMetrics::register(&registry).unwrap();
+
+		drop(metrics.start_request_timer("x"));
+		metrics.on_request_error("x");
+
+		let families = registry.gather();
+		let metric = |name: &str| {
+			let family = families.iter().find(|family| family.get_name() == name).unwrap();
+			assert_eq!(1, family.get_metric().len());
+
+			let metric = family.get_metric()[0].clone();
+			assert_eq!("method", metric.get_label()[0].get_name());
+			assert_eq!("x", metric.get_label()[0].get_value());
+			metric
+		};
+
+		assert_eq!(
+			1,
+			metric("cumulus_relay_chain_rpc_request_duration")
+				.get_histogram()
+				.get_sample_count()
+		);
+		assert_eq!(1.0, metric("cumulus_relay_chain_rpc_request_errors").get_counter().get_value());
+	}
+}
@@ -17,15 +17,21 @@
 use backoff::{future::retry_notify, ExponentialBackoff};
 use cumulus_primitives_core::{
 	relay_chain::{
-		v2::{CommittedCandidateReceipt, OccupiedCoreAssumption, SessionIndex, ValidatorId},
-		Hash as PHash, Header as PHeader, InboundHrmpMessage,
+		runtime_api::ParachainHost,
+		v2::{
+			CandidateEvent, CandidateHash, CommittedCandidateReceipt, CoreIndex, CoreState,
+			DisputeState, OccupiedCoreAssumption, OldV1SessionInfo, SessionIndex, SessionInfo,
+			ValidationCodeHash, ValidatorId,
+		},
+		Block as PBlock, BlockNumber as PBlockNumber, Hash as PHash, Header as PHeader,
+		InboundHrmpMessage,
 	},
 	InboundDownwardMessage, ParaId, PersistedValidationData,
 };
 use cumulus_relay_chain_interface::{RelayChainError, RelayChainResult};
 use futures::{
 	channel::mpsc::{Receiver, Sender},
-	StreamExt,
+	future, Stream, StreamExt,
 };
 use jsonrpsee::{
 	core::{
@@ -40,20 +46,31 @@ use parity_scale_codec::{Decode, Encode};
 use polkadot_service::TaskManager;
 use sc_client_api::StorageData;
 use sc_rpc_api::{state::ReadProof, system::Health};
+use sp_api::RuntimeApiInfo;
+use sp_consensus::BlockStatus;
+use sp_consensus_babe::{BabeGenesisConfiguration, Epoch, Slot};
 use sp_core::sp_std::collections::btree_map::BTreeMap;
-use sp_runtime::DeserializeOwned;
-use sp_storage::StorageKey;
+use sp_finality_grandpa::GRANDPA_ENGINE_ID;
+use sp_runtime::{generic::SignedBlock, DeserializeOwned, Justifications};
+use sp_storage::{well_known_keys, PrefixedStorageKey, StorageKey};
+use sp_version::RuntimeVersion;
 use std::sync::Arc;
+use substrate_prometheus_endpoint::Registry;
 use tokio::sync::mpsc::{
 	channel as tokio_channel, Receiver as TokioReceiver, Sender as TokioSender,
 };
 
 pub use url::Url;
 
+use crate::metrics::RelayChainRpcMetrics;
+
 const LOG_TARGET: &str = "relay-chain-rpc-client";
 
 const NOTIFICATION_CHANNEL_SIZE_LIMIT: usize = 20;
 
+/// The `ParachainHost` runtime API version that introduced `staging_get_disputes`.
+const DISPUTES_API_VERSION: u32 = 3;
+
 /// Client that maps RPC methods and deserializes results
 #[derive(Clone)]
 pub struct RelayChainRpcClient {
@@ -65,6 +82,9 @@ pub struct RelayChainRpcClient {
 
 	/// Channel to communicate with the RPC worker
 	to_worker_channel: TokioSender<NotificationRegisterMessage>,
+
+	/// Prometheus metrics of the requests, if a registry was given
+	metrics: Option<RelayChainRpcMetrics>,
 }
 
 /// Worker messages to register new notification listeners
@@ -92,9 +112,12 @@ struct RpcStreamWorker {
 }
 
 /// Entry point to create [`RelayChainRpcClient`] and start a worker that distributes notifications.
+///
+/// If a `prometheus_registry` is given, metrics about the requests are registered in it.
 pub async fn create_client_and_start_worker(
 	url: Url,
 	task_manager: &mut TaskManager,
+	prometheus_registry: Option<&Registry>,
 ) -> RelayChainResult<RelayChainRpcClient> {
 	tracing::info!(target: LOG_TARGET, url = %url.to_string(), "Initializing RPC Client");
 	let ws_client = WsClientBuilder::default().build(url.as_str()).await?;
@@ -105,7 +128,11 @@ pub async fn create_client_and_start_worker(
 
 	let (worker, sender) =
 		RpcStreamWorker::new(imported_head_stream, best_head_stream, finalized_head_stream);
-	let client = RelayChainRpcClient::new(ws_client, sender).await?;
+	let metrics = prometheus_registry
+		.map(RelayChainRpcMetrics::register)
+		.transpose()
+		.map_err(|err| RelayChainError::GenericError(err.to_string()))?;
+	let client = RelayChainRpcClient::new(ws_client, sender, metrics).await?;
 
 	task_manager
 		.spawn_essential_handle()
@@ -114,11 +141,125 @@ pub async fn create_client_and_start_worker(
 	Ok(client)
 }
 
+/// Convert the error of a failed request to `method` into a [`RelayChainError`].
+fn rpc_call_error(method: &str, err: JsonRpseeError) -> RelayChainError {
+	match err {
+		JsonRpseeError::RequestTimeout => RelayChainError::RpcTimeout(method.to_string()),
+		err => RelayChainError::RpcCallError(method.to_string(), err),
+	}
+}
+
+/// Find the index of the core in `cores` that is scheduled for the parachain `para_id`.
+fn scheduled_core_for_para(cores: &[CoreState], para_id: ParaId) -> Option<CoreIndex> {
+	cores
+		.iter()
+		.position(|core| match core {
+			CoreState::Scheduled(scheduled) => scheduled.para_id == para_id,
+			_ => false,
+		})
+		.map(|index| CoreIndex(index as u32))
+}
+
+/// Only keep the candidate events that concern the parachain `para_id`.
+fn filter_candidate_events_for_para(
+	events: Vec<CandidateEvent>,
+	para_id: ParaId,
+) -> Vec<CandidateEvent> {
+	events
+		.into_iter()
+		.filter(|event| {
+			let receipt = match event {
+				CandidateEvent::CandidateBacked(receipt, ..) |
+				CandidateEvent::CandidateIncluded(receipt, ..) |
+				CandidateEvent::CandidateTimedOut(receipt, ..) => receipt,
+			};
+			receipt.descriptor.para_id == para_id
+		})
+		.collect()
+}
+
+/// Map each inbound HRMP channel to the number of messages in it.
+fn count_inbound_hrmp_messages(
+	contents: BTreeMap<ParaId, Vec<InboundHrmpMessage>>,
+) -> BTreeMap<ParaId, usize> {
+	contents
+		.into_iter()
+		.map(|(sender, messages)| (sender, messages.len()))
+		.collect()
+}
+
+/// Decode the SCALE encoded `result` of a runtime call made through `state_call`.
+fn decode_runtime_call_result<R: Decode>(result: sp_core::Bytes) -> RelayChainResult<R> {
+	Decode::decode(&mut &*result.0).map_err(Into::into)
+}
+
+/// Return at most `limit` of the `messages`, skipping the first `start` of them.
+fn page_of_downward_messages(
+	messages: Vec<InboundDownwardMessage>,
+	start: usize,
+	limit: usize,
+) -> Vec<InboundDownwardMessage> {
+	messages.into_iter().skip(start).take(limit).collect()
+}
+
+/// Derive the [`BlockStatus`] of a block the node has the header of from the result of reading
+/// the hash of the runtime code at that block.
+///
+/// Nodes answer state queries for blocks with pruned state with a call error.
+fn block_status_from_code_hash(
+	code_hash: Result<Option<PHash>, RelayChainError>,
+) -> Result<BlockStatus, RelayChainError> {
+	match code_hash {
+		Ok(Some(_)) => Ok(BlockStatus::InChainWithState),
+		Ok(None) | Err(RelayChainError::RpcCallError(_, JsonRpseeError::Call(_))) =>
+			Ok(BlockStatus::InChainPruned),
+		Err(err) => Err(err),
+	}
+}
+
+/// Decode the hash of the block finalized by the SCALE encoded GRANDPA `justification`.
+///
+/// An encoded justification starts with the round number, followed by the commit whose first
+/// field is the hash of the finalized block.
+fn grandpa_justification_target(justification: &[u8]) -> Option<PHash> {
+	<(u64, PHash)>::decode(&mut &justification[..])
+		.ok()
+		.map(|(_round, target)| target)
+}
+
+/// Pair the finalized `header` with the encoded GRANDPA `justification` that finalized it.
+///
+/// Returns `None` if the header is unknown or the justification does not finalize it.
+fn header_with_justification(
+	header: Option<PHeader>,
+	justification: Vec<u8>,
+) -> Option<(PHeader, Justifications)> {
+	let header = header?;
+	if grandpa_justification_target(&justification)? != header.hash() {
+		return None
+	}
+
+	Some((header, Justifications::from((GRANDPA_ENGINE_ID, justification))))
+}
+
+/// Check whether the runtime described by `runtime_version` exposes the disputes of the
+/// `ParachainHost` runtime API.
+fn has_disputes_api(runtime_version: &RuntimeVersion) -> bool {
+	runtime_version
+		.api_version(&<dyn ParachainHost<PBlock>>::ID)
+		.map_or(false, |version| version >= DISPUTES_API_VERSION)
+}
+
+/// Distribute a header notification to all `senders`.
+///
+/// Returns an error if the subscription was closed or yielded an error. `kind` names the
+/// notification stream in the logged error.
 fn handle_event_distribution(
 	event: Option<Result<PHeader, JsonRpseeError>>,
 	senders: &mut Vec<Sender<PHeader>>,
+	kind: &'static str,
 ) -> Result<(), String> {
-	match event {
+	let result = match event {
 		Some(Ok(header)) => {
 			senders.retain_mut(|e| {
 				match e.try_send(header.clone()) {
@@ -138,7 +279,18 @@ fn handle_event_distribution(
 		},
 		None => Err("RPC Subscription closed.".to_string()),
 		Some(Err(err)) => Err(format!("Error in RPC subscription: {}", err)),
+	};
+
+	if let Err(err) = &result {
+		tracing::error!(
+			target: LOG_TARGET,
+			err,
+			kind,
+			"Encountered error while processing header notification. Stopping RPC Worker.",
+		);
 	}
+
+	result
 }
 
 impl RpcStreamWorker {
@@ -188,20 +340,17 @@ impl RpcStreamWorker {
 					}
 				},
 				import_event = import_sub.next() => {
-					if let Err(err) = handle_event_distribution(import_event, &mut self.imported_header_listeners) {
-						tracing::error!(target: LOG_TARGET, err, "Encountered error while processing imported header notification. Stopping RPC Worker.");
+					if handle_event_distribution(import_event, &mut self.imported_header_listeners, "imported").is_err() {
 						return;
 					}
 				},
 				best_header_event = best_head_sub.next() => {
-					if let Err(err) = handle_event_distribution(best_header_event, &mut self.best_header_listeners) {
-						tracing::error!(target: LOG_TARGET, err, "Encountered error while processing best header notification. Stopping RPC Worker.");
+					if handle_event_distribution(best_header_event, &mut self.best_header_listeners, "best").is_err() {
 						return;
 					}
 				}
 				finalized_event = finalized_sub.next() => {
-					if let Err(err) = handle_event_distribution(finalized_event, &mut self.finalized_header_listeners) {
-						tracing::error!(target: LOG_TARGET, err, "Encountered error while processing finalized header notification. Stopping RPC Worker.");
+					if handle_event_distribution(finalized_event, &mut self.finalized_header_listeners, "finalized").is_err() {
 						return;
 					}
 				}
@@ -215,11 +364,13 @@ impl RelayChainRpcClient {
 	async fn new(
 		ws_client: JsonRpcClient,
 		sender: TokioSender<NotificationRegisterMessage>,
+		metrics: Option<RelayChainRpcMetrics>,
 	) -> RelayChainResult<Self> {
 		let client = RelayChainRpcClient {
 			to_worker_channel: sender,
 			ws_client: Arc::new(ws_client),
 			retry_strategy: ExponentialBackoff::default(),
+			metrics,
 		};
 
 		Ok(client)
@@ -240,7 +391,7 @@ impl RelayChainRpcClient {
 			hash
 		};
 		let res = self
-			.request_tracing::<sp_core::Bytes, _>("state_call", params, |err| {
+			.request_tracing::<sp_core::Bytes, _>("state_call", method_name, params, |err| {
 				tracing::trace!(
 					target: LOG_TARGET,
 					%method_name,
@@ -250,7 +401,7 @@ impl RelayChainRpcClient {
 				);
 			})
 			.await?;
-		Decode::decode(&mut &*res.0).map_err(Into::into)
+		decode_runtime_call_result(res)
 	}
 
 	/// Subscribe to a notification stream via RPC
@@ -265,6 +416,7 @@ impl RelayChainRpcClient {
 		R: DeserializeOwned + std::fmt::Debug,
 	{
 		self.request_tracing(
+			method,
 			method,
 			params,
 			|e| tracing::trace!(target:LOG_TARGET, error = %e, %method, "Unable to complete RPC request"),
@@ -273,9 +425,13 @@ impl RelayChainRpcClient {
 	}
 
 	/// Perform RPC request
+	///
+	/// The request is recorded in the metrics under `metrics_label`. This allows runtime calls,
+	/// which all go through `state_call`, to be told apart by the runtime function they call.
 	async fn request_tracing<'a, R, OR>(
 		&self,
 		method: &'a str,
+		metrics_label: &str,
 		params: Option<ParamsSer<'a>>,
 		trace_error: OR,
 	) -> Result<R, RelayChainError>
@@ -283,6 +439,9 @@ impl RelayChainRpcClient {
 		R: DeserializeOwned + std::fmt::Debug,
 		OR: Fn(&jsonrpsee::core::Error),
 	{
+		let _timer =
+			self.metrics.as_ref().map(|metrics| metrics.start_request_timer(metrics_label));
+
 		retry_notify(
 			self.retry_strategy.clone(),
 			|| async {
@@ -297,13 +456,36 @@ impl RelayChainRpcClient {
 		.await
 		.map_err(|err| {
 			trace_error(&err);
-			RelayChainError::RpcCallError(method.to_string(), err)})
+			if let Some(metrics) = &self.metrics {
+				metrics.on_request_error(metrics_label);
+			}
+			rpc_call_error(method, err)})
 	}
 
 	pub async fn system_health(&self) -> Result<Health, RelayChainError> {
 		self.request("system_health", None).await
 	}
 
+	/// Submit the SCALE encoded `extrinsic` to the transaction pool of the relay chain node.
+	///
+	/// Returns the hash of the extrinsic. Extrinsics rejected by the pool are reported as
+	/// [`RelayChainError::RpcCallError`].
+	pub async fn author_submit_extrinsic(
+		&self,
+		extrinsic: sp_core::Bytes,
+	) -> Result<PHash, RelayChainError> {
+		let params = rpc_params!(extrinsic);
+		self.request("author_submitExtrinsic", params).await
+	}
+
+	pub async fn state_get_runtime_version(
+		&self,
+		at: Option<PHash>,
+	) -> Result<RuntimeVersion, RelayChainError> {
+		let params = rpc_params!(at);
+		self.request("state_getRuntimeVersion", params).await
+	}
+
 	pub async fn state_get_read_proof(
 		&self,
 		storage_keys: Vec<StorageKey>,
@@ -313,6 +495,16 @@ impl RelayChainRpcClient {
 		self.request("state_getReadProof", params).await
 	}
 
+	pub async fn state_get_child_read_proof(
+		&self,
+		child_storage_key: PrefixedStorageKey,
+		storage_keys: Vec<StorageKey>,
+		at: Option<PHash>,
+	) -> Result<ReadProof<PHash>, RelayChainError> {
+		let params = rpc_params!(child_storage_key, storage_keys, at);
+		self.request("state_getChildReadProof", params).await
+	}
+
 	pub async fn state_get_storage(
 		&self,
 		storage_key: StorageKey,
@@ -322,6 +514,48 @@ impl RelayChainRpcClient {
 		self.request("state_getStorage", params).await
 	}
 
+	/// Get up to `count` storage keys starting with `prefix` that come after `start_key`.
+	pub async fn state_get_keys_paged(
+		&self,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+		at: Option<PHash>,
+	) -> Result<Vec<StorageKey>, RelayChainError> {
+		let params = rpc_params!(prefix, count, start_key, at);
+		self.request("state_getKeysPaged", params).await
+	}
+
+	pub async fn state_get_storage_hash(
+		&self,
+		storage_key: StorageKey,
+		at: Option<PHash>,
+	) -> Result<Option<PHash>, RelayChainError> {
+		let params = rpc_params!(storage_key, at);
+		self.request("state_getStorageHash", params).await
+	}
+
+	/// Returns the [`BlockStatus`] of the block with the given `hash` on the node.
+	///
+	/// Blocks the node has no header for are [`BlockStatus::Unknown`]. For the other blocks we
+	/// probe the state by requesting the hash of the runtime code, which is cheap and always
+	/// present.
+	pub async fn block_status(&self, hash: PHash) -> Result<BlockStatus, RelayChainError> {
+		if self.chain_get_header(Some(hash)).await?.is_none() {
+			return Ok(BlockStatus::Unknown)
+		}
+
+		block_status_from_code_hash(
+			self.state_get_storage_hash(StorageKey(well_known_keys::CODE.to_vec()), Some(hash))
+				.await,
+		)
+	}
+
+	/// Returns whether the node has the block with the given `hash` and still has its state.
+	pub async fn has_state_at(&self, hash: PHash) -> Result<bool, RelayChainError> {
+		Ok(self.block_status(hash).await? == BlockStatus::InChainWithState)
+	}
+
 	pub async fn chain_get_head(&self) -> Result<PHash, RelayChainError> {
 		self.request("chain_getHead", None).await
 	}
@@ -334,6 +568,68 @@ impl RelayChainRpcClient {
 		self.request("chain_getHeader", params).await
 	}
 
+	/// Get the block numbers of the blocks with the given `hashes`.
+	///
+	/// The headers are requested concurrently. The returned numbers are in the same order as
+	/// `hashes`, with `None` for unknown blocks.
+	pub async fn block_numbers(
+		&self,
+		hashes: &[PHash],
+	) -> Result<Vec<Option<PBlockNumber>>, RelayChainError> {
+		future::try_join_all(hashes.iter().map(|hash| self.chain_get_header(Some(*hash))))
+			.await
+			.map(|headers| headers.into_iter().map(|header| header.map(|h| h.number)).collect())
+	}
+
+	pub async fn chain_get_block_hash(
+		&self,
+		block_number: Option<PBlockNumber>,
+	) -> Result<Option<PHash>, RelayChainError> {
+		let params = rpc_params!(block_number);
+		self.request("chain_getBlockHash", params).await
+	}
+
+	pub async fn chain_get_block(
+		&self,
+		hash: Option<PHash>,
+	) -> Result<Option<SignedBlock<PBlock>>, RelayChainError> {
+		let params = rpc_params!(hash);
+		self.request("chain_getBlock", params).await
+	}
+
+	/// Get the justifications of the block with the given `hash`.
+	///
+	/// There is no dedicated RPC method for this, so the full block is fetched.
+	/// Returns `None` if the block is unknown or has no justifications.
+	pub async fn chain_get_justifications(
+		&self,
+		hash: PHash,
+	) -> Result<Option<Justifications>, RelayChainError> {
+		self.chain_get_block(Some(hash))
+			.await
+			.map(|block| block.and_then(|block| block.justifications))
+	}
+
+	pub async fn babe_api_configuration(
+		&self,
+		at: PHash,
+	) -> Result<BabeGenesisConfiguration, RelayChainError> {
+		self.call_remote_runtime_function("BabeApi_configuration", at, None::<()>).await
+	}
+
+	pub async fn babe_api_current_epoch_start(&self, at: PHash) -> Result<Slot, RelayChainError> {
+		self.call_remote_runtime_function("BabeApi_current_epoch_start", at, None::<()>)
+			.await
+	}
+
+	pub async fn babe_api_current_epoch(&self, at: PHash) -> Result<Epoch, RelayChainError> {
+		self.call_remote_runtime_function("BabeApi_current_epoch", at, None::<()>).await
+	}
+
+	pub async fn babe_api_next_epoch(&self, at: PHash) -> Result<Epoch, RelayChainError> {
+		self.call_remote_runtime_function("BabeApi_next_epoch", at, None::<()>).await
+	}
+
 	pub async fn parachain_host_candidate_pending_availability(
 		&self,
 		at: PHash,
@@ -347,6 +643,63 @@ impl RelayChainRpcClient {
 		.await
 	}
 
+	pub async fn parachain_host_availability_cores(
+		&self,
+		at: PHash,
+	) -> Result<Vec<CoreState>, RelayChainError> {
+		self.call_remote_runtime_function("ParachainHost_availability_cores", at, None::<()>)
+			.await
+	}
+
+	/// Get the index of the availability core that is scheduled for the parachain `para_id`.
+	///
+	/// Returns `None` if no core is scheduled for the parachain.
+	pub async fn parachain_host_scheduled_core_for_para(
+		&self,
+		at: PHash,
+		para_id: ParaId,
+	) -> Result<Option<CoreIndex>, RelayChainError> {
+		self.parachain_host_availability_cores(at)
+			.await
+			.map(|cores| scheduled_core_for_para(&cores, para_id))
+	}
+
+	/// Get the candidates pending availability of all `para_ids`.
+	///
+	/// The queries are sent concurrently. The returned candidates are in the same order as
+	/// `para_ids`, with `None` for paras without a candidate pending availability.
+	pub async fn parachain_host_candidates_pending_availability(
+		&self,
+		at: PHash,
+		para_ids: &[ParaId],
+	) -> Result<Vec<Option<CommittedCandidateReceipt>>, RelayChainError> {
+		future::try_join_all(
+			para_ids
+				.iter()
+				.map(|para_id| self.parachain_host_candidate_pending_availability(at, *para_id)),
+		)
+		.await
+	}
+
+	pub async fn parachain_host_candidate_events(
+		&self,
+		at: PHash,
+	) -> Result<Vec<CandidateEvent>, RelayChainError> {
+		self.call_remote_runtime_function("ParachainHost_candidate_events", at, None::<()>)
+			.await
+	}
+
+	/// Get the candidate events of the given block that concern the parachain `para_id`.
+	pub async fn parachain_host_candidate_events_for_para(
+		&self,
+		at: PHash,
+		para_id: ParaId,
+	) -> Result<Vec<CandidateEvent>, RelayChainError> {
+		self.parachain_host_candidate_events(at)
+			.await
+			.map(|events| filter_candidate_events_for_para(events, para_id))
+	}
+
 	pub async fn parachain_host_session_index_for_child(
 		&self,
 		at: PHash,
@@ -355,6 +708,60 @@ impl RelayChainRpcClient {
 			.await
 	}
 
+	pub async fn parachain_host_session_info(
+		&self,
+		at: PHash,
+		index: SessionIndex,
+	) -> Result<Option<SessionInfo>, RelayChainError> {
+		self.call_remote_runtime_function("ParachainHost_session_info", at, Some(index))
+			.await
+	}
+
+	/// Get the session info for the given session index at the best block with the given
+	/// `number`.
+	///
+	/// Returns an error if there is no block with the given `number`.
+	pub async fn parachain_host_session_info_at_number(
+		&self,
+		number: PBlockNumber,
+		index: SessionIndex,
+	) -> Result<Option<SessionInfo>, RelayChainError> {
+		let at = self.chain_get_block_hash(Some(number)).await?.ok_or_else(|| {
+			RelayChainError::GenericError(format!("Unknown relay chain block number {}", number))
+		})?;
+
+		self.parachain_host_session_info(at, index).await
+	}
+
+	/// Get the session info for the given session index from runtimes with a `ParachainHost`
+	/// API version below 2.
+	///
+	/// These runtimes expose the session info in the old format under the same method name.
+	pub async fn parachain_host_session_info_before_version_2(
+		&self,
+		at: PHash,
+		index: SessionIndex,
+	) -> Result<Option<OldV1SessionInfo>, RelayChainError> {
+		self.call_remote_runtime_function("ParachainHost_session_info", at, Some(index))
+			.await
+	}
+
+	/// Get the disputes known to the runtime at `at`.
+	///
+	/// Runtimes with a `ParachainHost` API version below [`DISPUTES_API_VERSION`] don't expose
+	/// the disputes, for them an empty list is returned.
+	pub async fn parachain_host_staging_get_disputes(
+		&self,
+		at: PHash,
+	) -> Result<Vec<(SessionIndex, CandidateHash, DisputeState<PBlockNumber>)>, RelayChainError> {
+		if !has_disputes_api(&self.state_get_runtime_version(Some(at)).await?) {
+			return Ok(Vec::new())
+		}
+
+		self.call_remote_runtime_function("ParachainHost_staging_get_disputes", at, None::<()>)
+			.await
+	}
+
 	pub async fn parachain_host_validators(
 		&self,
 		at: PHash,
@@ -363,6 +770,14 @@ impl RelayChainRpcClient {
 			.await
 	}
 
+	pub async fn parachain_host_pvfs_require_precheck(
+		&self,
+		at: PHash,
+	) -> Result<Vec<ValidationCodeHash>, RelayChainError> {
+		self.call_remote_runtime_function("ParachainHost_pvfs_require_precheck", at, None::<()>)
+			.await
+	}
+
 	pub async fn parachain_host_persisted_validation_data(
 		&self,
 		at: PHash,
@@ -390,6 +805,17 @@ impl RelayChainRpcClient {
 		.await
 	}
 
+	/// Get the number of pending messages per inbound HRMP channel of the parachain `para_id`.
+	pub async fn parachain_host_inbound_hrmp_message_counts(
+		&self,
+		para_id: ParaId,
+		at: PHash,
+	) -> Result<BTreeMap<ParaId, usize>, RelayChainError> {
+		self.parachain_host_inbound_hrmp_channels_contents(para_id, at)
+			.await
+			.map(count_inbound_hrmp_messages)
+	}
+
 	pub async fn parachain_host_dmq_contents(
 		&self,
 		para_id: ParaId,
@@ -399,6 +825,22 @@ impl RelayChainRpcClient {
 			.await
 	}
 
+	/// Get at most `limit` downward messages of the parachain `para_id`, skipping the first
+	/// `start` messages of the queue.
+	///
+	/// The runtime API has no paging, so the whole queue is fetched and sliced locally.
+	pub async fn parachain_host_dmq_contents_paged(
+		&self,
+		para_id: ParaId,
+		at: PHash,
+		start: usize,
+		limit: usize,
+	) -> Result<Vec<InboundDownwardMessage>, RelayChainError> {
+		self.parachain_host_dmq_contents(para_id, at)
+			.await
+			.map(|messages| page_of_downward_messages(messages, start, limit))
+	}
+
 	fn send_register_message_to_worker(
 		&self,
 		message: NotificationRegisterMessage,
@@ -432,35 +874,466 @@ impl RelayChainRpcClient {
 		Ok(rx)
 	}
 
+	/// Get a stream of finalized headers together with their justifications.
+	///
+	/// Built on the GRANDPA justification subscription, so only blocks that were finalized with
+	/// a justification show up. The header of every justified block is fetched separately.
+	/// Justifications that can not be decoded or whose block is unknown are skipped.
+	pub async fn get_finalized_heads_with_justifications_stream(
+		&self,
+	) -> Result<impl Stream<Item = (PHeader, Justifications)>, RelayChainError> {
+		let client = self.clone();
+		let subscription = self
+			.ws_client
+			.subscribe::<sp_core::Bytes>(
+				"grandpa_subscribeJustifications",
+				None,
+				"grandpa_unsubscribeJustifications",
+			)
+			.await?;
+
+		Ok(subscription.filter_map(move |justification| {
+			let client = client.clone();
+			async move {
+				let justification = match justification {
+					Ok(justification) => justification.0,
+					Err(err) => {
+						tracing::debug!(target: LOG_TARGET, ?err, "Received invalid justification");
+						return None
+					},
+				};
+				let hash = grandpa_justification_target(&justification)?;
+
+				match client.chain_get_header(Some(hash)).await {
+					Ok(header) => header_with_justification(header, justification),
+					Err(err) => {
+						tracing::debug!(
+							target: LOG_TARGET,
+							?err,
+							%hash,
+							"Unable to fetch header of justified block",
+						);
+						None
+					},
+				}
+			}
+		}))
+	}
+
 	async fn subscribe_imported_heads(
 		ws_client: &JsonRpcClient,
 	) -> Result<Subscription<PHeader>, RelayChainError> {
-		Ok(ws_client
-			.subscribe::<PHeader>("chain_subscribeAllHeads", None, "chain_unsubscribeAllHeads")
-			.await?)
+		Self::subscribe_headers(ws_client, "chain_subscribeAllHeads", "chain_unsubscribeAllHeads")
+			.await
 	}
 
 	async fn subscribe_finalized_heads(
 		ws_client: &JsonRpcClient,
 	) -> Result<Subscription<PHeader>, RelayChainError> {
-		Ok(ws_client
-			.subscribe::<PHeader>(
-				"chain_subscribeFinalizedHeads",
-				None,
-				"chain_unsubscribeFinalizedHeads",
-			)
-			.await?)
+		Self::subscribe_headers(
+			ws_client,
+			"chain_subscribeFinalizedHeads",
+			"chain_unsubscribeFinalizedHeads",
+		)
+		.await
 	}
 
 	async fn subscribe_new_best_heads(
 		ws_client: &JsonRpcClient,
+	) -> Result<Subscription<PHeader>, RelayChainError> {
+		Self::subscribe_headers(ws_client, "chain_subscribeNewHeads", "chain_unsubscribeNewHeads")
+			.await
+	}
+
+	/// Subscribe to a header notification stream via RPC.
+	async fn subscribe_headers(
+		ws_client: &JsonRpcClient,
+		subscribe_method: &str,
+		unsubscribe_method: &str,
 	) -> Result<Subscription<PHeader>, RelayChainError> {
 		Ok(ws_client
-			.subscribe::<PHeader>(
-				"chain_subscribeNewHeads",
-				None,
-				"chain_unsubscribeFinalizedHeads",
-			)
+			.subscribe::<PHeader>(subscribe_method, None, unsubscribe_method)
 			.await?)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use cumulus_primitives_core::relay_chain::v2::{
+		CandidateDescriptor, CandidateReceipt, GroupIndex, HeadData, ScheduledCore, ValidationCode,
+		ValidatorIndex,
+	};
+	use jsonrpsee::types::error::{CallError, ErrorObject};
+	use sp_consensus_babe::AllowedSlots;
+
+	fn header(number: u32) -> PHeader {
+		PHeader {
+			parent_hash: Default::default(),
+			number,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: Default::default(),
+		}
+	}
+
+	fn candidate_receipt(para_id: u32) -> CandidateReceipt {
+		CandidateReceipt {
+			descriptor: CandidateDescriptor {
+				para_id: para_id.into(),
+				relay_parent: Default::default(),
+				collator: sp_core::sr25519::Public::from_raw([0; 32]).into(),
+				persisted_validation_data_hash: Default::default(),
+				pov_hash: Default::default(),
+				erasure_root: Default::default(),
+				signature: sp_core::sr25519::Signature::from_raw([0; 64]).into(),
+				para_head: Default::default(),
+				validation_code_hash: ValidationCode(Vec::new()).hash(),
+			},
+			commitments_hash: Default::default(),
+		}
+	}
+
+	#[test]
+	fn request_timeout_is_reported_as_rpc_timeout() {
+		assert!(matches!(
+			rpc_call_error("chain_getHead", JsonRpseeError::RequestTimeout),
+			RelayChainError::RpcTimeout(method) if method == "chain_getHead"
+		));
+		assert!(matches!(
+			rpc_call_error("chain_getHead", JsonRpseeError::Custom("failed".into())),
+			RelayChainError::RpcCallError(method, JsonRpseeError::Custom(_)) if method == "chain_getHead"
+		));
+	}
+
+	#[test]
+	fn block_status_is_derived_from_code_hash() {
+		let call_error = || {
+			JsonRpseeError::Call(CallError::Custom(ErrorObject::owned(
+				4003,
+				"State already discarded",
+				None::<()>,
+			)))
+		};
+
+		assert_eq!(
+			BlockStatus::InChainWithState,
+			block_status_from_code_hash(Ok(Some(PHash::repeat_byte(1)))).unwrap(),
+		);
+		assert_eq!(BlockStatus::InChainPruned, block_status_from_code_hash(Ok(None)).unwrap());
+		assert_eq!(
+			BlockStatus::InChainPruned,
+			block_status_from_code_hash(Err(RelayChainError::RpcCallError(
+				"state_getStorageHash".into(),
+				call_error(),
+			)))
+			.unwrap(),
+		);
+		assert!(matches!(
+			block_status_from_code_hash(Err(RelayChainError::RpcTimeout(
+				"state_getStorageHash".into()
+			))),
+			Err(RelayChainError::RpcTimeout(_))
+		));
+	}
+
+	#[test]
+	fn babe_configuration_is_decoded_from_runtime_call_result() {
+		let fixture = [
+			// slot_duration
+			&6000u64.to_le_bytes()[..],
+			// epoch_length
+			&600u64.to_le_bytes(),
+			// c
+			&1u64.to_le_bytes(),
+			&4u64.to_le_bytes(),
+			// genesis_authorities, compact length followed by (AuthorityId, weight)
+			&[4],
+			&[1; 32],
+			&1u64.to_le_bytes(),
+			// randomness
+			&[2; 32],
+			// allowed_slots, `PrimaryAndSecondaryVRFSlots`
+			&[2],
+		]
+		.concat();
+
+		let config: BabeGenesisConfiguration =
+			decode_runtime_call_result(sp_core::Bytes(fixture)).unwrap();
+
+		assert_eq!(
+			BabeGenesisConfiguration {
+				slot_duration: 6000,
+				epoch_length: 600,
+				c: (1, 4),
+				genesis_authorities: vec![(sp_core::sr25519::Public::from_raw([1; 32]).into(), 1)],
+				randomness: [2; 32],
+				allowed_slots: AllowedSlots::PrimaryAndSecondaryVRFSlots,
+			},
+			config,
+		);
+
+		assert!(decode_runtime_call_result::<BabeGenesisConfiguration>(sp_core::Bytes(vec![1, 2]))
+			.is_err());
+	}
+
+	#[test]
+	fn old_session_info_is_decoded_from_runtime_call_result() {
+		let fixture = [
+			// `Some`
+			&[1][..],
+			// validators, discovery_keys and assignment_keys, one key each
+			&[4],
+			&[1; 32],
+			&[4],
+			&[2; 32],
+			&[4],
+			&[3; 32],
+			// validator_groups, one group with validator 0
+			&[4, 4],
+			&0u32.to_le_bytes(),
+			// n_cores
+			&1u32.to_le_bytes(),
+			// zeroth_delay_tranche_width
+			&0u32.to_le_bytes(),
+			// relay_vrf_modulo_samples
+			&2u32.to_le_bytes(),
+			// n_delay_tranches
+			&40u32.to_le_bytes(),
+			// no_show_slots
+			&2u32.to_le_bytes(),
+			// needed_approvals
+			&30u32.to_le_bytes(),
+		]
+		.concat();
+
+		let info: OldV1SessionInfo =
+			decode_runtime_call_result::<Option<_>>(sp_core::Bytes(fixture))
+				.unwrap()
+				.unwrap();
+
+		assert_eq!(
+			vec![ValidatorId::from(sp_core::sr25519::Public::from_raw([1; 32]))],
+			info.validators,
+		);
+		assert_eq!(1, info.discovery_keys.len());
+		assert_eq!(1, info.assignment_keys.len());
+		assert_eq!(vec![vec![ValidatorIndex(0)]], info.validator_groups);
+		assert_eq!(
+			(1, 0, 2, 40, 2, 30),
+			(
+				info.n_cores,
+				info.zeroth_delay_tranche_width,
+				info.relay_vrf_modulo_samples,
+				info.n_delay_tranches,
+				info.no_show_slots,
+				info.needed_approvals,
+			),
+		);
+
+		assert!(decode_runtime_call_result::<Option<OldV1SessionInfo>>(sp_core::Bytes(vec![1, 4]))
+			.is_err());
+	}
+
+	#[test]
+	fn pvfs_requiring_precheck_are_decoded_from_runtime_call_result() {
+		let fixture = [&[8][..], &[1; 32], &[2; 32]].concat();
+
+		assert_eq!(
+			vec![
+				ValidationCodeHash::from(PHash::repeat_byte(1)),
+				ValidationCodeHash::from(PHash::repeat_byte(2)),
+			],
+			decode_runtime_call_result::<Vec<ValidationCodeHash>>(sp_core::Bytes(fixture)).unwrap(),
+		);
+		assert!(
+			decode_runtime_call_result::<Vec<ValidationCodeHash>>(sp_core::Bytes(vec![8])).is_err()
+		);
+	}
+
+	#[test]
+	fn signed_block_is_deserialized_from_rpc_response() {
+		let fixture = r#"{
+			"block": {
+				"header": {
+					"parentHash": "0x0101010101010101010101010101010101010101010101010101010101010101",
+					"number": "0x2a",
+					"stateRoot": "0x0202020202020202020202020202020202020202020202020202020202020202",
+					"extrinsicsRoot": "0x0303030303030303030303030303030303030303030303030303030303030303",
+					"digest": { "logs": [] }
+				},
+				"extrinsics": ["0x0c010203"]
+			},
+			"justifications": null
+		}"#;
+
+		let block: SignedBlock<PBlock> = serde_json::from_str(fixture).unwrap();
+
+		assert_eq!(
+			PHeader {
+				parent_hash: PHash::repeat_byte(1),
+				number: 42,
+				state_root: PHash::repeat_byte(2),
+				extrinsics_root: PHash::repeat_byte(3),
+				digest: Default::default(),
+			},
+			block.block.header,
+		);
+		assert_eq!(
+			vec![vec![12, 1, 2, 3]],
+			block.block.extrinsics.iter().map(Encode::encode).collect::<Vec<_>>(),
+		);
+		assert!(block.justifications.is_none());
+	}
+
+	#[test]
+	fn grandpa_justifications_are_deserialized_from_rpc_response() {
+		let fixture = r#"{
+			"block": {
+				"header": {
+					"parentHash": "0x0101010101010101010101010101010101010101010101010101010101010101",
+					"number": "0x2a",
+					"stateRoot": "0x0202020202020202020202020202020202020202020202020202020202020202",
+					"extrinsicsRoot": "0x0303030303030303030303030303030303030303030303030303030303030303",
+					"digest": { "logs": [] }
+				},
+				"extrinsics": []
+			},
+			"justifications": [[[70, 82, 78, 75], [1, 2, 3]]]
+		}"#;
+
+		let block: SignedBlock<PBlock> = serde_json::from_str(fixture).unwrap();
+		let justifications = block.justifications.unwrap();
+
+		assert_eq!(Some(&vec![1, 2, 3]), justifications.get(GRANDPA_ENGINE_ID));
+		assert_eq!(Justifications::from((GRANDPA_ENGINE_ID, vec![1, 2, 3])), justifications);
+	}
+
+	#[test]
+	fn disputes_api_is_detected_from_runtime_version() {
+		let runtime_version =
+			|apis: Vec<([u8; 8], u32)>| RuntimeVersion { apis: apis.into(), ..Default::default() };
+		let parachain_host = <dyn ParachainHost<PBlock>>::ID;
+
+		assert!(!has_disputes_api(&runtime_version(Vec::new())));
+		assert!(!has_disputes_api(&runtime_version(vec![(parachain_host, 2)])));
+		assert!(!has_disputes_api(&runtime_version(vec![([1; 8], DISPUTES_API_VERSION)])));
+		assert!(has_disputes_api(&runtime_version(vec![(parachain_host, DISPUTES_API_VERSION)])));
+	}
+
+	#[test]
+	fn scheduled_core_is_found_for_para() {
+		let scheduled = |para_id: u32| {
+			CoreState::Scheduled(ScheduledCore { para_id: para_id.into(), collator: None })
+		};
+		let cores = vec![CoreState::Free, scheduled(200), scheduled(100), CoreState::Free];
+
+		assert_eq!(Some(CoreIndex(2)), scheduled_core_for_para(&cores, ParaId::from(100)));
+		assert_eq!(Some(CoreIndex(1)), scheduled_core_for_para(&cores, ParaId::from(200)));
+		assert_eq!(None, scheduled_core_for_para(&cores, ParaId::from(300)));
+	}
+
+	#[test]
+	fn candidate_events_are_filtered_by_para() {
+		let events = vec![
+			CandidateEvent::CandidateBacked(
+				candidate_receipt(100),
+				HeadData(vec![1]),
+				CoreIndex(0),
+				GroupIndex(0),
+			),
+			CandidateEvent::CandidateIncluded(
+				candidate_receipt(200),
+				HeadData(vec![2]),
+				CoreIndex(1),
+				GroupIndex(1),
+			),
+			CandidateEvent::CandidateTimedOut(
+				candidate_receipt(100),
+				HeadData(vec![3]),
+				CoreIndex(0),
+			),
+		];
+
+		let filtered = filter_candidate_events_for_para(events.clone(), ParaId::from(100));
+		assert_eq!(vec![events[0].clone(), events[2].clone()], filtered);
+
+		assert!(filter_candidate_events_for_para(events, ParaId::from(300)).is_empty());
+	}
+
+	#[test]
+	fn inbound_hrmp_messages_are_counted_per_channel() {
+		let message = |sent_at| InboundHrmpMessage { sent_at, data: vec![1, 2, 3] };
+
+		let contents = BTreeMap::from([
+			(ParaId::from(200), vec![message(1)]),
+			(ParaId::from(300), vec![message(1), message(2), message(3)]),
+			(ParaId::from(400), Vec::new()),
+		]);
+
+		assert_eq!(
+			BTreeMap::from([
+				(ParaId::from(200), 1),
+				(ParaId::from(300), 3),
+				(ParaId::from(400), 0)
+			]),
+			count_inbound_hrmp_messages(contents),
+		);
+	}
+
+	#[test]
+	fn downward_messages_are_paged() {
+		let messages = (0..5)
+			.map(|sent_at| InboundDownwardMessage { sent_at, msg: vec![1] })
+			.collect::<Vec<_>>();
+
+		assert_eq!(messages[0..2].to_vec(), page_of_downward_messages(messages.clone(), 0, 2));
+		assert_eq!(messages[2..4].to_vec(), page_of_downward_messages(messages.clone(), 2, 2));
+		assert_eq!(messages[4..].to_vec(), page_of_downward_messages(messages.clone(), 4, 2));
+		assert!(page_of_downward_messages(messages, 6, 2).is_empty());
+	}
+
+	#[test]
+	fn headers_without_matching_justification_are_skipped() {
+		let justification = |header: &PHeader| {
+			(1u64, header.hash(), header.number, Vec::<u8>::new(), Vec::<PHeader>::new()).encode()
+		};
+
+		assert_eq!(
+			Some((header(1), Justifications::from((GRANDPA_ENGINE_ID, justification(&header(1)))))),
+			header_with_justification(Some(header(1)), justification(&header(1))),
+		);
+		assert_eq!(None, header_with_justification(Some(header(2)), justification(&header(1))));
+		assert_eq!(None, header_with_justification(None, justification(&header(1))));
+		assert_eq!(None, header_with_justification(Some(header(1)), vec![1, 2, 3]));
+	}
+
+	#[test]
+	fn event_distribution_forwards_headers_and_fails_on_error() {
+		let (tx, mut rx) = futures::channel::mpsc::channel(NOTIFICATION_CHANNEL_SIZE_LIMIT);
+		let mut senders = vec![tx];
+
+		assert!(handle_event_distribution(Some(Ok(header(1))), &mut senders, "test").is_ok());
+		assert!(handle_event_distribution(
+			Some(Err(JsonRpseeError::Custom("invalid header".into()))),
+			&mut senders,
+			"test",
+		)
+		.is_err());
+		assert!(handle_event_distribution(None, &mut senders, "test").is_err());
+
+		drop(senders);
+		let received = futures::executor::block_on(rx.collect::<Vec<_>>());
+		assert_eq!(vec![header(1)], received);
+	}
+
+	#[test]
+	fn event_distribution_removes_closed_senders() {
+		let (tx, rx) = futures::channel::mpsc::channel(NOTIFICATION_CHANNEL_SIZE_LIMIT);
+		let mut senders = vec![tx];
+		drop(rx);
+
+		assert!(handle_event_distribution(Some(Ok(header(1))), &mut senders, "test").is_ok());
+		assert!(senders.is_empty());
+	}
+}
@@ -27,6 +27,9 @@ use sp_keyring::AccountKeyring::*;
 use sp_runtime::{generic::BlockId, traits::Header as HeaderT};
 use std::{env, process::Command};
 
+/// The relay parent number that is passed to `validate_block`.
+const RELAY_PARENT_NUMBER: u32 = 1;
+
 fn call_validate_block_encoded_header(
 	parent_head: Header,
 	block_data: ParachainBlockData<Block>,
@@ -36,7 +39,7 @@ fn call_validate_block_encoded_header(
 		ValidationParams {
 			block_data: BlockData(block_data.encode()),
 			parent_head: HeadData(parent_head.encode()),
-			relay_parent_number: 1,
+			relay_parent_number: RELAY_PARENT_NUMBER,
 			relay_parent_storage_root,
 		},
 		&WASM_BINARY.expect("You need to build the WASM binaries to run the tests!"),
@@ -78,10 +81,31 @@ fn build_block_with_witness(
 	extra_extrinsics: Vec<UncheckedExtrinsic>,
 	parent_head: Header,
 	sproof_builder: RelayStateSproofBuilder,
+) -> TestBlockData {
+	build_block_with_witness_and_relay_parent_number(
+		client,
+		extra_extrinsics,
+		parent_head,
+		sproof_builder,
+		RELAY_PARENT_NUMBER,
+	)
+}
+
+/// Same as [`build_block_with_witness`], but declares the given `relay_parent_number` in the
+/// validation data of the block.
+///
+/// Passing anything else than [`RELAY_PARENT_NUMBER`] builds a block that is inconsistent with
+/// the [`ValidationParams`] used by [`call_validate_block`].
+fn build_block_with_witness_and_relay_parent_number(
+	client: &Client,
+	extra_extrinsics: Vec<UncheckedExtrinsic>,
+	parent_head: Header,
+	sproof_builder: RelayStateSproofBuilder,
+	relay_parent_number: u32,
 ) -> TestBlockData {
 	let (relay_parent_storage_root, _) = sproof_builder.clone().into_state_root_and_proof();
 	let mut validation_data = PersistedValidationData {
-		relay_parent_number: 1,
+		relay_parent_number,
 		parent_head: parent_head.encode().into(),
 		..Default::default()
 	};
@@ -222,6 +246,36 @@ fn validate_block_fails_on_invalid_validation_data() {
 	}
 }
 
+#[test]
+fn validate_block_fails_on_relay_parent_number_mismatch() {
+	sp_tracing::try_init_simple();
+
+	if env::var("RUN_TEST").is_ok() {
+		let (client, parent_head) = create_test_client();
+		let TestBlockData { block, validation_data } =
+			build_block_with_witness_and_relay_parent_number(
+				&client,
+				Vec::new(),
+				parent_head.clone(),
+				Default::default(),
+				RELAY_PARENT_NUMBER + 1,
+			);
+
+		call_validate_block(parent_head, block, validation_data.relay_parent_storage_root)
+			.unwrap_err();
+	} else {
+		let output = Command::new(env::current_exe().unwrap())
+			.args(&["validate_block_fails_on_relay_parent_number_mismatch", "--", "--nocapture"])
+			.env("RUN_TEST", "1")
+			.output()
+			.expect("Runs the test");
+		assert!(output.status.success());
+
+		assert!(dbg!(String::from_utf8(output.stderr).unwrap())
+			.contains("Relay parent number doesn't match"));
+	}
+}
+
 #[test]
 fn check_inherent_fails_on_validate_block_as_expected() {
 	sp_tracing::try_init_simple();
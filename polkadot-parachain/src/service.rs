@@ -268,7 +268,12 @@ async fn build_relay_chain_interface(
 ) -> RelayChainResult<(Arc<(dyn RelayChainInterface + 'static)>, Option<CollatorPair>)> {
 	match collator_options.relay_chain_rpc_url {
 		Some(relay_chain_url) => {
-			let client = create_client_and_start_worker(relay_chain_url, task_manager).await?;
+			let client = create_client_and_start_worker(
+				relay_chain_url,
+				task_manager,
+				parachain_config.prometheus_registry(),
+			)
+			.await?;
 			Ok((Arc::new(RelayChainRpcInterface::new(client)) as Arc<_>, None))
 		},
 		None => build_inprocess_relay_chain(
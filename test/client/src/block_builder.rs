@@ -15,17 +15,25 @@
 // along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::{Backend, Client};
-use cumulus_primitives_core::{ParachainBlockData, PersistedValidationData};
-use cumulus_primitives_parachain_inherent::{ParachainInherentData, INHERENT_IDENTIFIER};
+use codec::Encode;
+use cumulus_primitives_core::{
+	InboundDownwardMessage, InboundHrmpMessage, ParaId, ParachainBlockData, PersistedValidationData,
+};
+use cumulus_primitives_parachain_inherent::{
+	MessageQueueChain, ParachainInherentData, INHERENT_IDENTIFIER,
+};
 use cumulus_test_relay_sproof_builder::RelayStateSproofBuilder;
-use cumulus_test_runtime::{Block, GetLastTimestamp, Hash, Header};
+use cumulus_test_runtime::{BalancesCall, Block, GetLastTimestamp, Hash, Header};
 use polkadot_primitives::v2::{BlockNumber as PBlockNumber, Hash as PHash};
 use sc_block_builder::{BlockBuilder, BlockBuilderProvider};
 use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_keyring::Sr25519Keyring::{Alice, Bob};
 use sp_runtime::{
 	generic::BlockId,
 	traits::{Block as BlockT, Header as HeaderT},
 };
+use std::collections::BTreeMap;
 
 /// An extension for the Cumulus test client to init a block builder.
 pub trait InitBlockBuilder {
@@ -65,6 +73,21 @@ pub trait InitBlockBuilder {
 		relay_sproof_builder: RelayStateSproofBuilder,
 		timestamp: u64,
 	) -> sc_block_builder::BlockBuilder<Block, Client, Backend>;
+
+	/// Init a specific block builder at a specific block that works for the test runtime.
+	///
+	/// Same as [`InitBlockBuilder::init_block_builder_at`] besides that it passes the given
+	/// `downward_messages` and `horizontal_messages` to the parachain inherent. The message queue
+	/// chain heads in the `relay_sproof_builder` need to match these messages, otherwise the
+	/// inherent will fail.
+	fn init_block_builder_with_messages(
+		&self,
+		at: &BlockId<Block>,
+		validation_data: Option<PersistedValidationData<PHash, PBlockNumber>>,
+		relay_sproof_builder: RelayStateSproofBuilder,
+		downward_messages: Vec<InboundDownwardMessage>,
+		horizontal_messages: BTreeMap<ParaId, Vec<InboundHrmpMessage>>,
+	) -> sc_block_builder::BlockBuilder<Block, Client, Backend>;
 }
 
 fn init_block_builder<'a>(
@@ -73,6 +96,8 @@ fn init_block_builder<'a>(
 	validation_data: Option<PersistedValidationData<PHash, PBlockNumber>>,
 	relay_sproof_builder: RelayStateSproofBuilder,
 	timestamp: u64,
+	downward_messages: Vec<InboundDownwardMessage>,
+	horizontal_messages: BTreeMap<ParaId, Vec<InboundHrmpMessage>>,
 ) -> BlockBuilder<'a, Block, Client, Backend> {
 	let mut block_builder = client
 		.new_block_at(at, Default::default(), true)
@@ -101,8 +126,8 @@ fn init_block_builder<'a>(
 			&ParachainInherentData {
 				validation_data,
 				relay_chain_state,
-				downward_messages: Default::default(),
-				horizontal_messages: Default::default(),
+				downward_messages,
+				horizontal_messages,
 			},
 		)
 		.expect("Put validation function params failed");
@@ -136,11 +161,13 @@ impl InitBlockBuilder for Client {
 		validation_data: Option<PersistedValidationData<PHash, PBlockNumber>>,
 		relay_sproof_builder: RelayStateSproofBuilder,
 	) -> BlockBuilder<Block, Client, Backend> {
-		let last_timestamp = self.runtime_api().get_last_timestamp(at).expect("Get last timestamp");
-
-		let timestamp = last_timestamp + cumulus_test_runtime::MinimumPeriod::get();
-
-		init_block_builder(self, at, validation_data, relay_sproof_builder, timestamp)
+		self.init_block_builder_with_messages(
+			at,
+			validation_data,
+			relay_sproof_builder,
+			Default::default(),
+			Default::default(),
+		)
 	}
 
 	fn init_block_builder_with_timestamp(
@@ -150,8 +177,158 @@ impl InitBlockBuilder for Client {
 		relay_sproof_builder: RelayStateSproofBuilder,
 		timestamp: u64,
 	) -> sc_block_builder::BlockBuilder<Block, Client, Backend> {
-		init_block_builder(self, at, validation_data, relay_sproof_builder, timestamp)
+		init_block_builder(
+			self,
+			at,
+			validation_data,
+			relay_sproof_builder,
+			timestamp,
+			Default::default(),
+			Default::default(),
+		)
+	}
+
+	fn init_block_builder_with_messages(
+		&self,
+		at: &BlockId<Block>,
+		validation_data: Option<PersistedValidationData<PHash, PBlockNumber>>,
+		relay_sproof_builder: RelayStateSproofBuilder,
+		downward_messages: Vec<InboundDownwardMessage>,
+		horizontal_messages: BTreeMap<ParaId, Vec<InboundHrmpMessage>>,
+	) -> sc_block_builder::BlockBuilder<Block, Client, Backend> {
+		let last_timestamp = self.runtime_api().get_last_timestamp(at).expect("Get last timestamp");
+
+		let timestamp = last_timestamp + cumulus_test_runtime::MinimumPeriod::get();
+
+		init_block_builder(
+			self,
+			at,
+			validation_data,
+			relay_sproof_builder,
+			timestamp,
+			downward_messages,
+			horizontal_messages,
+		)
+	}
+}
+
+/// Create `count` downward messages with a payload of `size` bytes that were sent at `sent_at`.
+pub fn downward_messages(
+	count: u32,
+	size: usize,
+	sent_at: PBlockNumber,
+) -> Vec<InboundDownwardMessage> {
+	(0..count)
+		.map(|_| InboundDownwardMessage { sent_at, msg: vec![1; size] })
+		.collect()
+}
+
+/// Create `count_per_sender` horizontal messages with a payload of `size` bytes from each of the
+/// `senders`. All messages were sent at `sent_at`.
+pub fn horizontal_messages(
+	senders: impl IntoIterator<Item = ParaId>,
+	count_per_sender: u32,
+	size: usize,
+	sent_at: PBlockNumber,
+) -> BTreeMap<ParaId, Vec<InboundHrmpMessage>> {
+	senders
+		.into_iter()
+		.map(|sender| {
+			let messages = (0..count_per_sender)
+				.map(|_| InboundHrmpMessage { sent_at, data: vec![2; size] })
+				.collect();
+
+			(sender, messages)
+		})
+		.collect()
+}
+
+/// Create a [`RelayStateSproofBuilder`] for `para_id` whose message queues contain the given
+/// messages.
+///
+/// Sets the MQC head of the downward message queue and opens an inbound HRMP channel from every
+/// sender of `horizontal_messages`. The MQC head, message count, total size and limits of each
+/// channel are set to match its messages. The result can be passed together with the same
+/// messages to [`InitBlockBuilder::init_block_builder_with_messages`].
+pub fn sproof_with_messages(
+	para_id: ParaId,
+	downward_messages: &[InboundDownwardMessage],
+	horizontal_messages: &BTreeMap<ParaId, Vec<InboundHrmpMessage>>,
+) -> RelayStateSproofBuilder {
+	let mut sproof_builder = RelayStateSproofBuilder { para_id, ..Default::default() };
+
+	let mut dmq_mqc = MessageQueueChain::default();
+	downward_messages.iter().for_each(|msg| {
+		dmq_mqc.extend_downward(msg);
+	});
+	sproof_builder.dmq_mqc_head = Some(dmq_mqc.head());
+
+	for (sender, messages) in horizontal_messages {
+		let mut hrmp_mqc = MessageQueueChain::default();
+		messages.iter().for_each(|msg| {
+			hrmp_mqc.extend_hrmp(msg);
+		});
+
+		let msg_count = messages.len() as u32;
+		let total_size = messages.iter().map(|msg| msg.data.len() as u32).sum::<u32>();
+		let max_message_size = messages.iter().map(|msg| msg.data.len() as u32).max();
+
+		let channel = sproof_builder.upsert_inbound_channel(*sender);
+		channel.max_capacity = msg_count;
+		channel.max_total_size = total_size;
+		channel.max_message_size = max_message_size.unwrap_or_default();
+		channel.msg_count = msg_count;
+		channel.total_size = total_size;
+		channel.mqc_head = Some(hrmp_mqc.head());
 	}
+
+	sproof_builder
+}
+
+/// Returns the genesis header of `client`.
+pub fn genesis_header(client: &Client) -> Header {
+	client
+		.header(&BlockId::number(0))
+		.ok()
+		.flatten()
+		.expect("Genesis header exists; qed")
+}
+
+/// Init a block builder on top of `parent_header` and push `transfer_count` transfers from Alice
+/// to Bob.
+///
+/// The transfers are signed with the nonces `first_nonce..first_nonce + transfer_count`. The
+/// `parent_head` of the validation data is set to `parent_header`.
+pub fn init_block_builder_with_transfers<'a>(
+	client: &'a Client,
+	parent_header: &Header,
+	relay_parent_number: PBlockNumber,
+	first_nonce: u32,
+	transfer_count: u32,
+) -> BlockBuilder<'a, Block, Client, Backend> {
+	let validation_data = PersistedValidationData {
+		relay_parent_number,
+		parent_head: parent_header.encode().into(),
+		..Default::default()
+	};
+
+	let mut block_builder = client.init_block_builder_at(
+		&BlockId::Hash(parent_header.hash()),
+		Some(validation_data),
+		RelayStateSproofBuilder::default(),
+	);
+
+	for nonce in first_nonce..first_nonce + transfer_count {
+		let extrinsic = cumulus_test_service::construct_extrinsic(
+			client,
+			BalancesCall::transfer { dest: Bob.to_account_id().into(), value: 10_000 },
+			Alice.pair(),
+			Some(nonce),
+		);
+		block_builder.push(extrinsic).expect("Pushes transfer");
+	}
+
+	block_builder
 }
 
 /// Extension trait for the [`BlockBuilder`](sc_block_builder::BlockBuilder) to build directly a
@@ -175,3 +352,57 @@ impl<'a> BuildParachainBlockData for sc_block_builder::BlockBuilder<'a, Block, C
 		ParachainBlockData::new(header, extrinsics, storage_proof)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use cumulus_pallet_parachain_system::RelayChainStateProof;
+
+	#[test]
+	fn sproof_with_messages_is_read_back_from_the_relay_state_proof() {
+		let para_id = ParaId::from(100);
+
+		let dmq_messages = downward_messages(3, 32, 1);
+		let mut hrmp_messages = horizontal_messages([ParaId::from(200)], 2, 64, 1);
+		hrmp_messages.extend(horizontal_messages([ParaId::from(300)], 3, 16, 1));
+
+		let (relay_parent_storage_root, relay_chain_state) =
+			sproof_with_messages(para_id, &dmq_messages, &hrmp_messages)
+				.into_state_root_and_proof();
+
+		let snapshot =
+			RelayChainStateProof::new(para_id, relay_parent_storage_root, relay_chain_state)
+				.expect("Valid relay chain state proof")
+				.read_messaging_state_snapshot()
+				.expect("Valid messaging state in relay chain state proof");
+
+		let mut dmq_mqc = MessageQueueChain::default();
+		dmq_messages.iter().for_each(|msg| {
+			dmq_mqc.extend_downward(msg);
+		});
+		assert_eq!(dmq_mqc.head(), snapshot.dmq_mqc_head);
+
+		let hrmp_mqc_head = |sender: ParaId| {
+			let mut hrmp_mqc = MessageQueueChain::default();
+			hrmp_messages[&sender].iter().for_each(|msg| {
+				hrmp_mqc.extend_hrmp(msg);
+			});
+			hrmp_mqc.head()
+		};
+
+		let ingress = snapshot
+			.ingress_channels
+			.iter()
+			.map(|(sender, channel)| {
+				(*sender, channel.msg_count, channel.total_size, channel.mqc_head)
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(
+			vec![
+				(ParaId::from(200), 2, 128, Some(hrmp_mqc_head(ParaId::from(200)))),
+				(ParaId::from(300), 3, 48, Some(hrmp_mqc_head(ParaId::from(300)))),
+			],
+			ingress,
+		);
+	}
+}
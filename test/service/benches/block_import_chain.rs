@@ -0,0 +1,92 @@
+// This file is part of Cumulus.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use cumulus_test_client::{
+	genesis_header, init_block_builder_with_transfers, runtime::Block, sp_consensus::BlockOrigin,
+	Client, ClientBlockImportExt, DefaultTestClientBuilderExt, TestClientBuilder,
+	TestClientBuilderExt,
+};
+use futures::executor::block_on;
+
+/// Number of blocks in the chain that is imported.
+const CHAIN_LENGTH: u32 = 20;
+
+/// Number of transfers in each block of the chain.
+const TRANSFERS_PER_BLOCK: u32 = 50;
+
+/// Build and import a chain of [`CHAIN_LENGTH`] blocks full of transfers.
+///
+/// Each block is built on top of the previous one, so the state grows between the blocks.
+/// Returns the blocks in the order they need to be imported.
+fn build_chain(client: &mut Client) -> Vec<Block> {
+	let mut parent_header = genesis_header(client);
+
+	(1..=CHAIN_LENGTH)
+		.map(|relay_parent_number| {
+			let first_nonce = (relay_parent_number - 1) * TRANSFERS_PER_BLOCK;
+			let block = init_block_builder_with_transfers(
+				client,
+				&parent_header,
+				relay_parent_number,
+				first_nonce,
+				TRANSFERS_PER_BLOCK,
+			)
+			.build()
+			.expect("Builds the block")
+			.block;
+
+			block_on(client.import(BlockOrigin::Own, block.clone())).expect("Imports the block");
+			parent_header = block.header.clone();
+
+			block
+		})
+		.collect()
+}
+
+fn block_import_chain_benchmarks(c: &mut Criterion) {
+	sp_tracing::try_init_simple();
+
+	let chain = build_chain(&mut TestClientBuilder::new().build());
+
+	let mut group = c.benchmark_group("Block import");
+	group.sample_size(10);
+	group.throughput(Throughput::Elements((CHAIN_LENGTH * TRANSFERS_PER_BLOCK) as u64));
+
+	group.bench_function(
+		format!(
+			"import chain of {} blocks with {} transfers each",
+			CHAIN_LENGTH, TRANSFERS_PER_BLOCK
+		),
+		|b| {
+			b.iter_batched(
+				|| (TestClientBuilder::new().build(), chain.clone()),
+				|(mut client, chain)| {
+					for block in chain {
+						block_on(client.import(BlockOrigin::Own, block))
+							.expect("Imports the block");
+					}
+				},
+				BatchSize::SmallInput,
+			)
+		},
+	);
+}
+
+criterion_group!(benches, block_import_chain_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,112 @@
+// This file is part of Cumulus.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use cumulus_primitives_core::{
+	InboundDownwardMessage, InboundHrmpMessage, ParaId, PersistedValidationData,
+};
+use cumulus_primitives_parachain_inherent::ParachainInherentData;
+use cumulus_test_client::{downward_messages, horizontal_messages, sproof_with_messages};
+use std::collections::BTreeMap;
+
+const HRMP_CHANNELS: &[u32] = &[1, 10, 50];
+const DMQ_MESSAGES: &[u32] = &[10, 100];
+
+/// Number of messages in each inbound HRMP channel.
+const HRMP_MESSAGES_PER_CHANNEL: u32 = 10;
+
+/// Size of the payload of each message.
+const MESSAGE_SIZE: usize = 256;
+
+const RELAY_PARENT_NUMBER: u32 = 1;
+
+/// Create the [`ParachainInherentData`] for `para_id` that contains the given messages.
+///
+/// The message queue chain heads of the messages are put into the relay chain state proof, like
+/// the relay chain would do.
+fn create_parachain_inherent(
+	para_id: ParaId,
+	downward_messages: Vec<InboundDownwardMessage>,
+	horizontal_messages: BTreeMap<ParaId, Vec<InboundHrmpMessage>>,
+) -> ParachainInherentData {
+	let (relay_parent_storage_root, relay_chain_state) =
+		sproof_with_messages(para_id, &downward_messages, &horizontal_messages)
+			.into_state_root_and_proof();
+
+	ParachainInherentData {
+		validation_data: PersistedValidationData {
+			relay_parent_number: RELAY_PARENT_NUMBER,
+			relay_parent_storage_root,
+			..Default::default()
+		},
+		relay_chain_state,
+		downward_messages,
+		horizontal_messages,
+	}
+}
+
+fn parachain_inherent_benchmarks(c: &mut Criterion) {
+	sp_tracing::try_init_simple();
+
+	let para_id = ParaId::from(100);
+
+	let mut group = c.benchmark_group("Parachain inherent");
+
+	for hrmp_channels in HRMP_CHANNELS {
+		for dmq_messages in DMQ_MESSAGES {
+			let total_messages = dmq_messages + hrmp_channels * HRMP_MESSAGES_PER_CHANNEL;
+
+			group.throughput(Throughput::Elements(total_messages as u64));
+			group.bench_function(
+				BenchmarkId::new(
+					"create",
+					format!("{} hrmp channels, {} dmq messages", hrmp_channels, dmq_messages),
+				),
+				|b| {
+					b.iter_batched(
+						|| {
+							let senders =
+								(1..=*hrmp_channels).map(|i| ParaId::from(u32::from(para_id) + i));
+
+							(
+								downward_messages(*dmq_messages, MESSAGE_SIZE, RELAY_PARENT_NUMBER),
+								horizontal_messages(
+									senders,
+									HRMP_MESSAGES_PER_CHANNEL,
+									MESSAGE_SIZE,
+									RELAY_PARENT_NUMBER,
+								),
+							)
+						},
+						|(downward_messages, horizontal_messages)| {
+							create_parachain_inherent(
+								para_id,
+								downward_messages,
+								horizontal_messages,
+							)
+						},
+						BatchSize::SmallInput,
+					)
+				},
+			);
+		}
+	}
+}
+
+criterion_group!(benches, parachain_inherent_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,156 @@
+// This file is part of Cumulus.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use codec::Encode;
+use criterion::{
+	criterion_group, criterion_main,
+	measurement::{Measurement, ValueFormatter},
+	BenchmarkId, Criterion, Throughput,
+};
+use cumulus_test_client::{
+	genesis_header, init_block_builder_with_transfers, BuildParachainBlockData, Client,
+	DefaultTestClientBuilderExt, ParachainBlockData, TestClientBuilder, TestClientBuilderExt,
+};
+use sp_runtime::traits::Header as HeaderT;
+use std::time::Duration;
+
+const TRANSFER_COUNTS: &[u32] = &[10, 100];
+
+/// Upper bound for the size of the decompressed storage proof.
+const PROOF_BOMB_LIMIT: usize = 50 * 1024 * 1024;
+
+/// Build a parachain block on top of genesis that contains `transfer_count` transfers.
+fn build_parachain_block(client: &Client, transfer_count: u32) -> ParachainBlockData {
+	let parent_header = genesis_header(client);
+
+	init_block_builder_with_transfers(client, &parent_header, 1, 0, transfer_count)
+		.build_parachain_block(*parent_header.state_root())
+}
+
+/// Compress `encoded_proof` and return the ratio between the uncompressed and compressed size.
+fn compression_ratio(encoded_proof: &[u8]) -> f64 {
+	let compressed_proof = sp_maybe_compressed_blob::compress(encoded_proof, PROOF_BOMB_LIMIT)
+		.expect("Compresses the storage proof");
+
+	encoded_proof.len() as f64 / compressed_proof.len() as f64
+}
+
+/// A Criterion [`Measurement`] that reports the compression ratio instead of the time.
+///
+/// Can only be used with [`Bencher::iter_custom`](criterion::Bencher::iter_custom), where the
+/// routine returns the ratio multiplied by the number of iterations. The ratio of a given input
+/// is deterministic, so there is no need to compress the input in every iteration.
+struct CompressionRatio;
+
+impl Measurement for CompressionRatio {
+	type Intermediate = ();
+	type Value = f64;
+
+	fn start(&self) -> Self::Intermediate {}
+
+	fn end(&self, _: Self::Intermediate) -> Self::Value {
+		0.0
+	}
+
+	fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+		v1 + v2
+	}
+
+	fn zero(&self) -> Self::Value {
+		0.0
+	}
+
+	fn to_f64(&self, value: &Self::Value) -> f64 {
+		*value
+	}
+
+	fn formatter(&self) -> &dyn ValueFormatter {
+		&CompressionRatioFormatter
+	}
+}
+
+struct CompressionRatioFormatter;
+
+impl ValueFormatter for CompressionRatioFormatter {
+	fn scale_values(&self, _: f64, _: &mut [f64]) -> &'static str {
+		"x"
+	}
+
+	fn scale_throughputs(&self, _: f64, _: &Throughput, _: &mut [f64]) -> &'static str {
+		"x"
+	}
+
+	fn scale_for_machines(&self, _: &mut [f64]) -> &'static str {
+		"x"
+	}
+}
+
+fn pov_compression_benchmarks(c: &mut Criterion) {
+	sp_tracing::try_init_simple();
+
+	let client = TestClientBuilder::new().build();
+
+	let mut group = c.benchmark_group("PoV compression");
+
+	for transfer_count in TRANSFER_COUNTS {
+		let parachain_block = build_parachain_block(&client, *transfer_count);
+		let encoded_proof = parachain_block.storage_proof().encode();
+
+		group.throughput(Throughput::Bytes(encoded_proof.len() as u64));
+		group.bench_with_input(
+			BenchmarkId::new("compress storage proof", format!("{} transfers", transfer_count)),
+			&encoded_proof,
+			|b, encoded_proof| {
+				b.iter(|| sp_maybe_compressed_blob::compress(encoded_proof, PROOF_BOMB_LIMIT))
+			},
+		);
+	}
+}
+
+fn pov_compression_ratio_benchmarks(c: &mut Criterion<CompressionRatio>) {
+	sp_tracing::try_init_simple();
+
+	let client = TestClientBuilder::new().build();
+
+	let mut group = c.benchmark_group("PoV compression ratio");
+
+	for transfer_count in TRANSFER_COUNTS {
+		let parachain_block = build_parachain_block(&client, *transfer_count);
+		let ratio = compression_ratio(&parachain_block.storage_proof().encode());
+
+		group.bench_function(
+			BenchmarkId::new("compression ratio", format!("{} transfers", transfer_count)),
+			|b| b.iter_custom(|iters| ratio * iters as f64),
+		);
+	}
+}
+
+criterion_group!(benches, pov_compression_benchmarks);
+criterion_group! {
+	name = ratio_benches;
+	// The ratio is the same in every sample, so there is nothing to warm up, no distribution to
+	// plot and the minimal number of samples is enough.
+	config = Criterion::default()
+		.with_measurement(CompressionRatio)
+		.without_plots()
+		.warm_up_time(Duration::from_millis(1))
+		.measurement_time(Duration::from_millis(1))
+		.sample_size(10);
+	targets = pov_compression_ratio_benchmarks
+}
+criterion_main!(benches, ratio_benches);
@@ -0,0 +1,150 @@
+// This file is part of Cumulus.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use cumulus_pallet_parachain_system::RelayChainStateProof;
+use cumulus_primitives_core::{
+	relay_chain::{v2::HrmpChannelId, Hash as PHash},
+	AbridgedHrmpChannel, ParaId,
+};
+use cumulus_test_client::{horizontal_messages, sproof_with_messages};
+use sp_state_machine::StorageProof;
+
+/// Number of inbound HRMP channels of the parachain.
+const INBOUND_CHANNELS: &[u32] = &[0, 10, 50, 100];
+
+/// Number of outbound HRMP channels of the parachain.
+const OUTBOUND_CHANNELS: &[u32] = &[0, 10, 100];
+
+/// Number of messages in each inbound HRMP channel.
+const MESSAGES_PER_CHANNEL: u32 = 10;
+
+/// Size of each message in the inbound HRMP channels.
+const MESSAGE_SIZE: usize = 1024;
+
+/// Create a relay chain state proof for `para_id` with `inbound_channels` inbound and
+/// `outbound_channels` outbound HRMP channels.
+///
+/// `set_validation_data` reads the index and every channel of both directions from the proof, so
+/// both numbers change the shape and the size of the proof. The downward messages are not part
+/// of the proof, only their MQC head, so the downward message queue is left empty.
+///
+/// Each inbound channel is filled up to its limits with [`MESSAGES_PER_CHANNEL`] messages of
+/// [`MESSAGE_SIZE`] bytes. The outbound channels have the same limits and are empty.
+fn create_relay_state_proof(
+	para_id: ParaId,
+	inbound_channels: u32,
+	outbound_channels: u32,
+) -> (PHash, StorageProof) {
+	let other = |i: u32| ParaId::from(u32::from(para_id) + i + 1);
+
+	let mut sproof_builder = sproof_with_messages(
+		para_id,
+		&[],
+		&horizontal_messages(
+			(0..inbound_channels).map(other),
+			MESSAGES_PER_CHANNEL,
+			MESSAGE_SIZE,
+			0,
+		),
+	);
+
+	let recipients = (0..outbound_channels).map(other).collect::<Vec<_>>();
+	for recipient in &recipients {
+		sproof_builder.hrmp_channels.insert(
+			HrmpChannelId { sender: para_id, recipient: *recipient },
+			AbridgedHrmpChannel {
+				max_capacity: MESSAGES_PER_CHANNEL,
+				max_total_size: MESSAGES_PER_CHANNEL * MESSAGE_SIZE as u32,
+				max_message_size: MESSAGE_SIZE as u32,
+				msg_count: 0,
+				total_size: 0,
+				mqc_head: None,
+			},
+		);
+	}
+	sproof_builder.hrmp_egress_channel_index = Some(recipients);
+
+	sproof_builder.into_state_root_and_proof()
+}
+
+/// Benchmarks the relay chain state proof verification and the reads that are done by
+/// `set_validation_data`.
+///
+/// Processing of the downward and horizontal messages is not part of this benchmark.
+fn relay_state_proof_benchmarks(c: &mut Criterion) {
+	sp_tracing::try_init_simple();
+
+	let para_id = ParaId::from(100);
+
+	let mut group = c.benchmark_group("Relay chain state proof");
+
+	for inbound_channels in INBOUND_CHANNELS {
+		for outbound_channels in OUTBOUND_CHANNELS {
+			let (relay_parent_storage_root, relay_chain_state) =
+				create_relay_state_proof(para_id, *inbound_channels, *outbound_channels);
+
+			tracing::info!(
+				inbound_channels,
+				outbound_channels,
+				proof_size = relay_chain_state.encoded_size(),
+				"Created relay chain state proof",
+			);
+
+			group.bench_function(
+				BenchmarkId::new(
+					"verify and read",
+					format!(
+						"{} inbound channels, {} outbound channels",
+						inbound_channels, outbound_channels
+					),
+				),
+				|b| {
+					b.iter_batched(
+						|| relay_chain_state.clone(),
+						|relay_chain_state| {
+							let relay_state_proof = RelayChainStateProof::new(
+								para_id,
+								relay_parent_storage_root,
+								relay_chain_state,
+							)
+							.expect("Invalid relay chain state proof");
+
+							relay_state_proof
+								.read_upgrade_go_ahead_signal()
+								.expect("Invalid upgrade go ahead signal");
+							relay_state_proof
+								.read_upgrade_restriction_signal()
+								.expect("Invalid upgrade restriction signal");
+							relay_state_proof
+								.read_abridged_host_configuration()
+								.expect("Invalid host configuration in relay chain state proof");
+							relay_state_proof
+								.read_messaging_state_snapshot()
+								.expect("Invalid messaging state in relay chain state proof")
+						},
+						BatchSize::SmallInput,
+					)
+				},
+			);
+		}
+	}
+}
+
+criterion_group!(benches, relay_state_proof_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,218 @@
+// This file is part of Cumulus.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use codec::{Decode, Encode};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use cumulus_primitives_core::{ParaId, PersistedValidationData};
+use cumulus_test_client::{
+	downward_messages, genesis_header, horizontal_messages,
+	runtime::{Header, WASM_BINARY},
+	sproof_with_messages, BlockData, BuildParachainBlockData, Client, DefaultTestClientBuilderExt,
+	HeadData, InitBlockBuilder, TestClientBuilder, TestClientBuilderExt, ValidationParams,
+	ValidationResult,
+};
+use sc_executor_common::{
+	runtime_blob::RuntimeBlob,
+	wasm_runtime::{WasmInstance, WasmModule},
+};
+use sc_executor_wasmtime::{Config, InstantiationStrategy, Semantics};
+use sp_io::TestExternalities;
+use sp_runtime::{generic::BlockId, traits::Header as HeaderT};
+
+/// Number of downward messages and number of horizontal messages put into the block.
+const MESSAGE_COUNTS: &[u32] = &[10, 100, 1000];
+
+/// Number of inbound HRMP channels the horizontal messages are spread evenly across.
+const HRMP_SENDERS: u32 = 10;
+
+/// Size of the payload of each message.
+const MESSAGE_SIZE: usize = 256;
+
+/// The relay parent number that is passed to `validate_block`.
+const RELAY_PARENT_NUMBER: u32 = 1;
+
+/// The para id of the test runtime.
+const PARA_ID: u32 = 100;
+
+/// Build a parachain block on top of `parent_header` that receives `message_count` downward
+/// messages and `message_count` horizontal messages.
+///
+/// Returns the encoded [`ValidationParams`] to validate the block with.
+fn build_block_with_messages(
+	client: &Client,
+	parent_header: &Header,
+	message_count: u32,
+) -> Vec<u8> {
+	let para_id = ParaId::from(PARA_ID);
+	let downward_messages = downward_messages(message_count, MESSAGE_SIZE, RELAY_PARENT_NUMBER);
+	let horizontal_messages = horizontal_messages(
+		(1..=HRMP_SENDERS).map(|i| ParaId::from(PARA_ID + i)),
+		message_count / HRMP_SENDERS,
+		MESSAGE_SIZE,
+		RELAY_PARENT_NUMBER,
+	);
+	let sproof_builder = sproof_with_messages(para_id, &downward_messages, &horizontal_messages);
+
+	let (relay_parent_storage_root, _) = sproof_builder.clone().into_state_root_and_proof();
+	let validation_data = PersistedValidationData {
+		relay_parent_number: RELAY_PARENT_NUMBER,
+		parent_head: parent_header.encode().into(),
+		..Default::default()
+	};
+
+	let block_builder = client.init_block_builder_with_messages(
+		&BlockId::Hash(parent_header.hash()),
+		Some(validation_data),
+		sproof_builder,
+		downward_messages,
+		horizontal_messages,
+	);
+
+	let parachain_block = block_builder.build_parachain_block(*parent_header.state_root());
+
+	ValidationParams {
+		block_data: BlockData(parachain_block.encode()),
+		parent_head: HeadData(parent_header.encode()),
+		relay_parent_number: RELAY_PARENT_NUMBER,
+		relay_parent_storage_root,
+	}
+	.encode()
+}
+
+/// Compile `wasm_blob` with wasmtime, the way the validation of a block is executed on the
+/// validators, using the given `instantiation_strategy`.
+fn create_runtime(
+	wasm_blob: &[u8],
+	instantiation_strategy: InstantiationStrategy,
+) -> Box<dyn WasmModule> {
+	let blob =
+		RuntimeBlob::uncompress_if_needed(wasm_blob).expect("RuntimeBlob uncompress & parse");
+	let config = Config {
+		allow_missing_func_imports: true,
+		cache_path: None,
+		semantics: Semantics {
+			extra_heap_pages: 1024,
+			instantiation_strategy,
+			deterministic_stack_limit: None,
+			canonicalize_nans: false,
+			parallel_compilation: true,
+			max_memory_size: None,
+		},
+	};
+
+	Box::new(
+		sc_executor_wasmtime::create_runtime::<sp_io::SubstrateHostFunctions>(blob, config)
+			.expect("Creates the wasmtime runtime"),
+	)
+}
+
+/// Validate the block once and check that `validate_block` succeeds.
+///
+/// Makes a broken fixture fail before it is benchmarked, instead of producing timings for a
+/// failing validation.
+fn assert_validates(instance: &mut dyn WasmInstance, validation_params: &[u8]) {
+	let result = TestExternalities::default()
+		.execute_with(|| instance.call_export("validate_block", validation_params))
+		.expect("Validates the block");
+	let result =
+		ValidationResult::decode(&mut &result[..]).expect("Decodes the `ValidationResult`");
+
+	tracing::info!(head_data = ?result.head_data, "Validated the block");
+}
+
+/// Benchmarks `validate_block` of blocks that receive downward and horizontal messages.
+///
+/// The test runtime sets `DmpMessageHandler` and `XcmpMessageHandler` to `()`, so the messages are
+/// only checked against the message queue chain heads in the relay chain state proof and then
+/// dropped. This measures the MQC verification of the messages, not their processing.
+///
+/// Every block is validated in two ways, to quantify the instantiation overhead:
+///
+/// - `instance per call` creates a new instance for every call with the copy-on-write pooling
+///   strategy, like validators do.
+/// - `reused instance` calls `validate_block` repeatedly on one instance that is created up front.
+///   The legacy instance reuse strategy restores the globals and data segments of the instance
+///   after each call, so every call starts from the same state.
+fn validate_block_messages_benchmarks(c: &mut Criterion) {
+	sp_tracing::try_init_simple();
+
+	let client = TestClientBuilder::new().build();
+	let parent_header = genesis_header(&client);
+
+	let wasm_blob = WASM_BINARY.expect("You need to build the WASM binaries to run the benchmark!");
+	let pooling_runtime = create_runtime(wasm_blob, InstantiationStrategy::PoolingCopyOnWrite);
+	let reusing_runtime = create_runtime(wasm_blob, InstantiationStrategy::LegacyInstanceReuse);
+
+	let mut group = c.benchmark_group("Validate block with messages");
+	group.sample_size(10);
+
+	for message_count in MESSAGE_COUNTS {
+		let validation_params = build_block_with_messages(&client, &parent_header, *message_count);
+		let mut instance = reusing_runtime.new_instance().expect("Instantiates the runtime");
+		assert_validates(&mut *instance, &validation_params);
+
+		tracing::info!(
+			message_count,
+			encoded_params_size = validation_params.len(),
+			"Built parachain block with downward and horizontal messages",
+		);
+
+		let parameter = format!("{0} downward and {0} horizontal messages", message_count);
+
+		group.throughput(Throughput::Elements(2 * *message_count as u64));
+		group.bench_with_input(
+			BenchmarkId::new("validate block, instance per call", &parameter),
+			&validation_params,
+			|b, validation_params| {
+				b.iter_batched(
+					TestExternalities::default,
+					|mut ext| {
+						let mut instance =
+							pooling_runtime.new_instance().expect("Instantiates the runtime");
+						ext.execute_with(|| {
+							instance
+								.call_export("validate_block", validation_params)
+								.expect("Validates the block")
+						})
+					},
+					BatchSize::SmallInput,
+				)
+			},
+		);
+		group.bench_with_input(
+			BenchmarkId::new("validate block, reused instance", &parameter),
+			&validation_params,
+			|b, validation_params| {
+				b.iter_batched(
+					TestExternalities::default,
+					|mut ext| {
+						ext.execute_with(|| {
+							instance
+								.call_export("validate_block", validation_params)
+								.expect("Validates the block")
+						})
+					},
+					BatchSize::SmallInput,
+				)
+			},
+		);
+	}
+}
+
+criterion_group!(benches, validate_block_messages_benchmarks);
+criterion_main!(benches);
@@ -59,7 +59,11 @@ use sp_arithmetic::traits::SaturatedConversion;
 use sp_blockchain::HeaderBackend;
 use sp_core::{Pair, H256};
 use sp_keyring::Sr25519Keyring;
-use sp_runtime::{codec::Encode, generic, traits::BlakeTwo256};
+use sp_runtime::{
+	codec::Encode,
+	generic,
+	traits::{BlakeTwo256, Block as BlockT},
+};
 use sp_state_machine::BasicExternalities;
 use sp_trie::PrefixedMemoryDB;
 use std::sync::Arc;
@@ -183,7 +187,7 @@ async fn build_relay_chain_interface(
 	task_manager: &mut TaskManager,
 ) -> RelayChainResult<Arc<dyn RelayChainInterface + 'static>> {
 	if let Some(relay_chain_url) = collator_options.relay_chain_rpc_url {
-		let client = create_client_and_start_worker(relay_chain_url, task_manager).await?;
+		let client = create_client_and_start_worker(relay_chain_url, task_manager, None).await?;
 		return Ok(Arc::new(RelayChainRpcInterface::new(client)) as Arc<_>)
 	}
 
@@ -730,8 +734,13 @@ impl TestNode {
 }
 
 /// Fetch account nonce for key pair
-pub fn fetch_nonce(client: &Client, account: sp_core::sr25519::Public) -> u32 {
-	let best_hash = client.chain_info().best_hash;
+pub fn fetch_nonce<B, C>(client: &C, account: sp_core::sr25519::Public) -> u32
+where
+	B: BlockT,
+	C: HeaderBackend<B> + ProvideRuntimeApi<B>,
+	C::Api: AccountNonceApi<B, runtime::AccountId, runtime::Index>,
+{
+	let best_hash = client.info().best_hash;
 	client
 		.runtime_api()
 		.account_nonce(&generic::BlockId::Hash(best_hash), account.into())
@@ -739,17 +748,26 @@ pub fn fetch_nonce(client: &Client, account: sp_core::sr25519::Public) -> u32 {
 }
 
 /// Construct an extrinsic that can be applied to the test runtime.
-pub fn construct_extrinsic(
-	client: &Client,
+///
+/// The extrinsic is signed with the given `nonce` or with the current nonce of `caller` if no
+/// `nonce` is given. Works with any client of the test runtime, like the one of the test client.
+pub fn construct_extrinsic<B, C>(
+	client: &C,
 	function: impl Into<runtime::RuntimeCall>,
 	caller: sp_core::sr25519::Pair,
 	nonce: Option<u32>,
-) -> runtime::UncheckedExtrinsic {
+) -> runtime::UncheckedExtrinsic
+where
+	B: BlockT<Hash = Hash>,
+	C: HeaderBackend<B> + ProvideRuntimeApi<B>,
+	C::Api: AccountNonceApi<B, runtime::AccountId, runtime::Index>,
+{
 	let function = function.into();
-	let current_block_hash = client.info().best_hash;
-	let current_block = client.info().best_number.saturated_into();
-	let genesis_block = client.hash(0).unwrap().unwrap();
-	let nonce = nonce.unwrap_or_else(|| fetch_nonce(client, caller.public()));
+	let info = client.info();
+	let current_block_hash = info.best_hash;
+	let current_block = info.best_number.saturated_into();
+	let genesis_block = info.genesis_hash;
+	let nonce = nonce.unwrap_or_else(|| fetch_nonce::<B, _>(client, caller.public()));
 	let period = runtime::BlockHashCount::get()
 		.checked_next_power_of_two()
 		.map(|c| c / 2)
@@ -0,0 +1,89 @@
+// Copyright 2022 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use codec::Encode;
+use cumulus_test_client::{
+	genesis_header, init_block_builder_with_transfers, BuildParachainBlockData,
+	DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
+};
+use sp_runtime::traits::Header as HeaderT;
+
+/// Number of transfers in the block.
+const TRANSFER_COUNT: u32 = 20;
+
+/// Upper bound for the encoded storage proof of a block with [`TRANSFER_COUNT`] transfers.
+///
+/// This is the guard against runtime or trie changes that grow the proof. It is still an
+/// estimate and not derived from a measured proof size, so it only catches the proof growing by
+/// an order of magnitude. Set it to the `storage_proof_size` logged by this test plus a margin
+/// and note both numbers here.
+const MAX_STORAGE_PROOF_SIZE: usize = 64 * 1024;
+
+/// How much bigger, in percent, the storage proof of [`TRANSFER_COUNT`] transfers may be than the
+/// storage proof of a single transfer.
+///
+/// This only catches a proof that grows with the number of transfers. A change that grows the
+/// proof of every block is caught by [`MAX_STORAGE_PROOF_SIZE`].
+///
+/// All transfers are from Alice to Bob and touch the same storage items. Only the first access of
+/// an item reads from the backend and ends up in the proof, so the proof size should not depend
+/// on the number of transfers.
+const MAX_GROWTH_PERCENT: usize = 10;
+
+/// Build a block on top of genesis with `transfer_count` transfers and return the size of its
+/// encoded storage proof.
+fn proof_size_of_transfers(transfer_count: u32) -> usize {
+	let client = TestClientBuilder::new().build();
+	let parent_header = genesis_header(&client);
+
+	let parachain_block =
+		init_block_builder_with_transfers(&client, &parent_header, 1, 0, transfer_count)
+			.build_parachain_block(*parent_header.state_root());
+
+	parachain_block.storage_proof().encode().len()
+}
+
+#[test]
+fn storage_proof_size_of_transfers_stays_bounded() {
+	sp_tracing::try_init_simple();
+
+	let single_transfer_size = proof_size_of_transfers(1);
+	let storage_proof_size = proof_size_of_transfers(TRANSFER_COUNT);
+
+	tracing::info!(
+		single_transfer_size,
+		storage_proof_size,
+		"Built parachain block with {} transfers",
+		TRANSFER_COUNT,
+	);
+
+	assert!(
+		storage_proof_size <= MAX_STORAGE_PROOF_SIZE,
+		"Storage proof of {} bytes exceeds the limit of {} bytes",
+		storage_proof_size,
+		MAX_STORAGE_PROOF_SIZE,
+	);
+
+	let max_size = single_transfer_size * (100 + MAX_GROWTH_PERCENT) / 100;
+	assert!(
+		storage_proof_size <= max_size,
+		"Storage proof of {} transfers is {} bytes, more than {}% above the {} bytes of a single transfer",
+		TRANSFER_COUNT,
+		storage_proof_size,
+		MAX_GROWTH_PERCENT,
+		single_transfer_size,
+	);
+}